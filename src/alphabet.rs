@@ -0,0 +1,253 @@
+use std::{
+    fmt::{self, Debug, Display},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    str::FromStr,
+};
+
+/// A symbol table and radix driving [EncodedU128]'s [Display]/[FromStr] impls.
+///
+/// Implement this for a unit struct to get a base-N id type for free: [EncodedU128]'s encode,
+/// decode and round-trip all derive from [Alphabet::RADIX] and the two mapping functions, so
+/// downstream crates can define their own alphabet without reimplementing any formatting logic.
+/// See [crate::cb32u128::Crockford] for a worked example, including check-digit support layered
+/// on top.
+pub trait Alphabet {
+    /// Number of symbols in the alphabet; digits are computed modulo this value.
+    const RADIX: u32;
+
+    /// Label [EncodedU128]'s [Debug] impl prints the value under, so different alphabets (and
+    /// aliases like [crate::cb32u128::Cb32u128]) stay distinguishable in debug output.
+    const NAME: &'static str;
+
+    /// Maps a digit value (always `< RADIX`) to its textual symbol.
+    fn digit_to_char(digit: u32) -> char;
+
+    /// Maps a textual symbol back to a digit value.
+    fn char_to_digit(c: char) -> CharDigit;
+}
+
+/// The outcome of looking up a character via [Alphabet::char_to_digit].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CharDigit {
+    /// `c` is a regular digit with this value.
+    Valid(u32),
+    /// `c` is reserved (e.g. for a check symbol), not a regular digit.
+    CheckDigit,
+    /// `c` is not part of the alphabet at all.
+    Invalid,
+}
+
+/// A `u128` encoded/decoded via an [Alphabet] `A`.
+///
+/// [crate::cb32u128::Cb32u128] is defined as `EncodedU128<Crockford>`; pick a different
+/// alphabet to get a differently-encoded id type with the same machinery, including the
+/// [crate::PrefixedId] and `rand` integrations.
+pub struct EncodedU128<A>(u128, PhantomData<A>);
+
+// Deriving these would add a `where A: Trait` bound for each of them, even though `A` only ever
+// appears inside `PhantomData<A>` and never actually needs to implement anything - so they're
+// implemented by hand, keyed purely off the inner `u128`, instead.
+
+impl<A> Clone for EncodedU128<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A> Copy for EncodedU128<A> {}
+
+impl<A> PartialEq for EncodedU128<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<A> Eq for EncodedU128<A> {}
+
+impl<A> Hash for EncodedU128<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<A> EncodedU128<A> {
+    pub const fn from_u128(value: u128) -> Self {
+        EncodedU128(value, PhantomData)
+    }
+
+    pub const fn into_u128(self) -> u128 {
+        self.0
+    }
+}
+
+impl<A> From<u128> for EncodedU128<A> {
+    fn from(value: u128) -> Self {
+        EncodedU128::from_u128(value)
+    }
+}
+
+impl<A> From<EncodedU128<A>> for u128 {
+    fn from(value: EncodedU128<A>) -> Self {
+        value.into_u128()
+    }
+}
+
+impl<A: Alphabet> Display for EncodedU128<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // u128 in the smallest supported radix (2) never needs more than 128 digits
+        let mut digits = [0u32; 128];
+        let mut len = 0;
+        let mut x = self.0;
+
+        loop {
+            digits[len] = (x % A::RADIX as u128) as u32;
+            x /= A::RADIX as u128;
+            len += 1;
+            if x == 0 {
+                break;
+            }
+        }
+
+        for &digit in digits[..len].iter().rev() {
+            write!(f, "{}", A::digit_to_char(digit))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<A: Alphabet> Debug for EncodedU128<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({self})", A::NAME)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EncodedU128ParseError {
+    InvalidDigit(char),
+    UnsupportedCheckDigit(char),
+    CheckDigitMismatch { expected: u8, found: u8 },
+}
+
+impl<A: Alphabet> FromStr for EncodedU128<A> {
+    type Err = EncodedU128ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut res = 0u128;
+
+        for c in s.chars() {
+            let digit_value = match A::char_to_digit(c) {
+                CharDigit::Valid(v) => v as u128,
+                CharDigit::CheckDigit => {
+                    return Err(EncodedU128ParseError::UnsupportedCheckDigit(c))
+                }
+                CharDigit::Invalid => return Err(EncodedU128ParseError::InvalidDigit(c)),
+            };
+
+            // matches the old Cb32u128 behavior: silently wraps on overflow rather than erroring
+            res = res
+                .wrapping_mul(A::RADIX as u128)
+                .wrapping_add(digit_value);
+        }
+
+        Ok(EncodedU128(res, PhantomData))
+    }
+}
+
+#[cfg(feature = "rand")]
+mod rand_impls {
+    use rand::distributions::{Distribution, Standard};
+
+    use super::EncodedU128;
+
+    impl<A> Distribution<EncodedU128<A>> for Standard {
+        fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> EncodedU128<A> {
+            EncodedU128::from_u128(rng.gen())
+        }
+    }
+}
+
+/// Plain lowercase base-36 (`0-9`, `a-z`).
+pub struct Base36Lower;
+
+const BASE36_LOWER_MAPPING: [char; 36] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i',
+    'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+];
+
+impl Alphabet for Base36Lower {
+    const RADIX: u32 = 36;
+    const NAME: &'static str = "Base36Lower";
+
+    fn digit_to_char(digit: u32) -> char {
+        BASE36_LOWER_MAPPING[digit as usize]
+    }
+
+    fn char_to_digit(c: char) -> CharDigit {
+        let lower = c.to_ascii_lowercase();
+        match BASE36_LOWER_MAPPING.iter().position(|&m| m == lower) {
+            Some(i) => CharDigit::Valid(i as u32),
+            None => CharDigit::Invalid,
+        }
+    }
+}
+
+/// URL-safe base64 (`A-Z a-z 0-9 - _`) over the 128-bit value, i.e. a plain radix-64 encoding,
+/// not `base64`-crate-style byte-stream encoding.
+pub struct Base64Url;
+
+const BASE64URL_MAPPING: [char; 64] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l',
+    'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1', '2', '3', '4',
+    '5', '6', '7', '8', '9', '-', '_',
+];
+
+impl Alphabet for Base64Url {
+    const RADIX: u32 = 64;
+    const NAME: &'static str = "Base64Url";
+
+    fn digit_to_char(digit: u32) -> char {
+        BASE64URL_MAPPING[digit as usize]
+    }
+
+    fn char_to_digit(c: char) -> CharDigit {
+        match BASE64URL_MAPPING.iter().position(|&m| m == c) {
+            Some(i) => CharDigit::Valid(i as u32),
+            None => CharDigit::Invalid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base36_lower_round_trips() {
+        type Id = EncodedU128<Base36Lower>;
+        let id = Id::from_u128(123456789);
+        assert_eq!(id.to_string(), id.to_string().to_lowercase());
+        assert_eq!(id.to_string().parse(), Ok(id));
+    }
+
+    #[test]
+    fn base64_url_round_trips() {
+        type Id = EncodedU128<Base64Url>;
+        let id = Id::from_u128(u128::MAX);
+        assert_eq!(id.to_string().parse(), Ok(id));
+    }
+
+    #[test]
+    fn debug_includes_the_alphabet_name_so_different_alphabets_stay_distinguishable() {
+        assert_eq!(
+            format!("{:?}", EncodedU128::<Base36Lower>::from_u128(5)),
+            "Base36Lower(5)"
+        );
+        assert_eq!(
+            format!("{:?}", EncodedU128::<Base64Url>::from_u128(5)),
+            "Base64Url(F)"
+        );
+    }
+}
@@ -1,104 +1,37 @@
-use std::{
-    fmt::{self, Debug, Display},
-    str::FromStr,
-};
-
-/// u128 that is represented ([std::fmt::Display] and [std::str::FromStr] impls) with [Crockford's
-/// Base32](https://www.crockford.com/base32.html) without check digit
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Cb32u128(u128);
-
-const BITS: usize = u128::BITS as usize;
-const DIGIT_BITS: usize = 5; // log2(32)
-
-impl Display for Cb32u128 {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // heavily based on https://github.com/archer884/crockford/blob/3662a0a328a888068a368d3558cea6cde85d73c0/src/encoding.rs#L43
-
-        // 992u128 in binary in 5 bit segments, represented in base32 as Z0
-        // 000 00000 00000 00000 00000 00000 00000 00000 00000 000000 00000 11111 00000
-        // \_/ \___/
-        //  3    5
+use crate::alphabet::{Alphabet, CharDigit, EncodedU128, EncodedU128ParseError};
 
-        const REM_BITS: usize = BITS % DIGIT_BITS;
-
-        const REM_SHIFT: usize = BITS - REM_BITS;
-        const DIGIT_SHIFT: usize = BITS - DIGIT_BITS;
-
-        const STOP_BIT: u128 = 1 << REM_SHIFT;
-
-        let mut x = self.0;
-
-        if x == 0 {
-            Display::fmt(&CROCKFORD_MAPPING[0], f)?;
-            return Ok(());
-        }
-
-        match (x >> REM_SHIFT) as usize {
-            0 => {
-                x <<= REM_BITS;
-                x |= 1;
+/// [Crockford's Base32](https://www.crockford.com/base32.html) alphabet, including the 5
+/// symbols reserved for the check digit (`* ~ $ = U`).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Crockford;
 
-                fn round_to_multiple_of_digit_bits(x: u32) -> u32 {
-                    let num_multiples = x / DIGIT_BITS as u32;
-                    num_multiples * DIGIT_BITS as u32
-                }
+impl Alphabet for Crockford {
+    const RADIX: u32 = 32;
+    // Keeps `Cb32u128`'s `Debug` output (e.g. "Cb32u128(0)") matching its public type alias
+    // instead of leaking the `EncodedU128<Crockford>` implementation detail.
+    const NAME: &'static str = "Cb32u128";
 
-                x <<= round_to_multiple_of_digit_bits(x.leading_zeros());
-            }
+    fn digit_to_char(digit: u32) -> char {
+        CROCKFORD_MAPPING[digit as usize]
+    }
 
-            i => {
-                x <<= REM_BITS;
-                x |= 1;
-                Display::fmt(&CROCKFORD_MAPPING[i], f)?;
-            }
+    fn char_to_digit(c: char) -> CharDigit {
+        if !c.is_ascii() {
+            return CharDigit::Invalid;
         }
-
-        while x != STOP_BIT {
-            let i = (x >> DIGIT_SHIFT) as usize;
-            Display::fmt(&CROCKFORD_MAPPING[i], f)?;
-            x <<= DIGIT_BITS;
+        match CROCKFORD_REVERSE_MAPPING[c as usize] {
+            CrmEntry::Valid(v) => CharDigit::Valid(v as u32),
+            CrmEntry::CheckDigit => CharDigit::CheckDigit,
+            CrmEntry::Invalid => CharDigit::Invalid,
         }
-
-        Ok(())
     }
 }
 
-impl Debug for Cb32u128 {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Cb32u128({})", self)
-    }
-}
+/// u128 that is represented ([std::fmt::Display] and [std::str::FromStr] impls) with
+/// [Crockford's Base32](https://www.crockford.com/base32.html) without check digit
+pub type Cb32u128 = EncodedU128<Crockford>;
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-pub enum Cb32u128ParseError {
-    InvalidDigit(char),
-    UnsupportedCheckDigit(char),
-}
-
-impl FromStr for Cb32u128 {
-    type Err = Cb32u128ParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut res = 0u128;
-
-        for c in s.chars() {
-            if !c.is_ascii() {
-                return Err(Cb32u128ParseError::InvalidDigit(c));
-            }
-            let digit_value = match CROCKFORD_REVERSE_MAPPING[c as usize] {
-                CrmEntry::Valid(digit_value) => digit_value,
-                CrmEntry::CheckDigit => return Err(Cb32u128ParseError::UnsupportedCheckDigit(c)),
-                CrmEntry::Invalid => return Err(Cb32u128ParseError::InvalidDigit(c)),
-            };
-
-            res <<= DIGIT_BITS; // doesn't matter for the initial iteration
-            res |= digit_value;
-        }
-
-        Ok(Cb32u128(res))
-    }
-}
+pub type Cb32u128ParseError = EncodedU128ParseError;
 
 const CROCKFORD_MAPPING: [char; 32] = [
     '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J',
@@ -167,14 +100,120 @@ const CROCKFORD_REVERSE_MAPPING: [CrmEntry; 256] = const {
     entries
 };
 
-#[cfg(feature = "rand")]
-mod rand_impls {
-    use super::*;
-    use rand::distributions::{Distribution, Standard};
+/// The 5 symbols Crockford's spec reserves for the check digit, for values 32..=36 of `value %
+/// 37`.
+const CROCKFORD_CHECK_MAPPING: [char; 5] = ['*', '~', '$', '=', 'U'];
 
-    impl Distribution<Cb32u128> for Standard {
-        fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Cb32u128 {
-            Cb32u128(rng.gen())
+/// Computes the Crockford check symbol (`value % 37`) for `value`.
+fn check_symbol(value: u128) -> char {
+    match (value % 37) as usize {
+        d @ 0..=31 => CROCKFORD_MAPPING[d],
+        d => CROCKFORD_CHECK_MAPPING[d - 32],
+    }
+}
+
+/// Maps a character back to its `0..37` check value, accepting both regular digits and the
+/// dedicated check symbols (case-insensitively, same as [CROCKFORD_REVERSE_MAPPING]).
+fn check_value(c: char) -> Option<u128> {
+    if !c.is_ascii() {
+        return None;
+    }
+    match CROCKFORD_REVERSE_MAPPING[c as usize] {
+        CrmEntry::Valid(v) => Some(v),
+        CrmEntry::CheckDigit => {
+            let upper = c.to_ascii_uppercase();
+            CROCKFORD_CHECK_MAPPING
+                .iter()
+                .position(|&m| m == upper)
+                .map(|i| 32 + i as u128)
+        }
+        CrmEntry::Invalid => None,
+    }
+}
+
+impl Cb32u128 {
+    /// Encodes `self` the same way as [std::fmt::Display], with a trailing Crockford check
+    /// symbol (see [Cb32u128::from_str_with_check]).
+    pub fn to_string_with_check(&self) -> String {
+        let mut s = self.to_string();
+        s.push(check_symbol(self.into_u128()));
+        s
+    }
+
+    /// Parses a string produced by [Cb32u128::to_string_with_check], verifying the trailing
+    /// check symbol against `value % 37` and returning
+    /// [Cb32u128ParseError::CheckDigitMismatch] if it doesn't match. This catches common
+    /// transcription errors that plain [std::str::FromStr] would silently accept as a different
+    /// id.
+    pub fn from_str_with_check(s: &str) -> Result<Self, Cb32u128ParseError> {
+        let mut chars = s.chars();
+        let check_char = chars
+            .next_back()
+            .ok_or(Cb32u128ParseError::InvalidDigit('\0'))?;
+        let value: Cb32u128 = chars.as_str().parse()?;
+
+        let found = check_value(check_char).ok_or(Cb32u128ParseError::InvalidDigit(check_char))?;
+        let expected = value.into_u128() % 37;
+
+        if found != expected {
+            return Err(Cb32u128ParseError::CheckDigitMismatch {
+                expected: expected as u8,
+                found: found as u8,
+            });
+        }
+
+        Ok(value)
+    }
+
+    /// Encodes the inner `u128` as an unsigned LEB128 varint, for compact storage in binary
+    /// formats where the textual form of up to 26 chars (see [std::fmt::Display]) would be
+    /// wasteful.
+    pub fn encode_to(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        crate::leb128::encode(self.into_u128(), &mut out);
+        out
+    }
+
+    /// Decodes a value previously produced by [Cb32u128::encode_to], returning it along with
+    /// any unconsumed trailing bytes.
+    pub fn decode_from(bytes: &[u8]) -> Result<(Self, &[u8]), crate::leb128::DecodeError> {
+        let (value, rest) = crate::leb128::decode(bytes)?;
+        Ok((Cb32u128::from_u128(value), rest))
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Cb32u128;
+
+    impl Serialize for Cb32u128 {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            if serializer.is_human_readable() {
+                serializer.collect_str(self)
+            } else {
+                crate::leb128::serde_support::serialize(self.into_u128(), serializer)
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Cb32u128 {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                let s = String::deserialize(deserializer)?;
+                s.parse()
+                    .map_err(|e: super::Cb32u128ParseError| D::Error::custom(format!("{e:?}")))
+            } else {
+                let value = crate::leb128::serde_support::deserialize(deserializer)?;
+                Ok(Cb32u128::from_u128(value))
+            }
         }
     }
 }
@@ -187,23 +226,23 @@ mod tests {
 
     #[test]
     fn formatting_works() {
-        assert_eq!(Cb32u128(0).to_string(), "0");
-        assert_eq!(Cb32u128(32).to_string(), "10");
-        assert_eq!(Cb32u128(0b11111_00000).to_string(), "Z0");
+        assert_eq!(Cb32u128::from(0).to_string(), "0");
+        assert_eq!(Cb32u128::from(32).to_string(), "10");
+        assert_eq!(Cb32u128::from(0b11111_00000).to_string(), "Z0");
         assert_eq!(
-            Cb32u128(u128::MAX).to_string(),
+            Cb32u128::from(u128::MAX).to_string(),
             "7ZZZZZZZZZZZZZZZZZZZZZZZZZ"
         );
     }
 
     #[test]
     fn parsing_works() {
-        assert_eq!("0".parse(), Ok(Cb32u128(0)));
-        assert_eq!("10".parse(), Ok(Cb32u128(32)));
-        assert_eq!("Z0".parse(), Ok(Cb32u128(0b11111_00000)));
+        assert_eq!("0".parse(), Ok(Cb32u128::from(0)));
+        assert_eq!("10".parse(), Ok(Cb32u128::from(32)));
+        assert_eq!("Z0".parse(), Ok(Cb32u128::from(0b11111_00000)));
         assert_eq!(
             "7ZZZZZZZZZZZZZZZZZZZZZZZZZ".parse(),
-            Ok(Cb32u128(u128::MAX))
+            Ok(Cb32u128::from(u128::MAX))
         );
 
         assert_eq!(
@@ -224,4 +263,58 @@ mod tests {
             Err(Cb32u128ParseError::UnsupportedCheckDigit('*'))
         );
     }
+
+    #[test]
+    fn check_digit_round_trips() {
+        let with_check = Cb32u128::from(0b11111_00000).to_string_with_check();
+        assert_eq!(with_check, "Z0Y");
+        assert_eq!(
+            Cb32u128::from_str_with_check(&with_check),
+            Ok(Cb32u128::from(0b11111_00000))
+        );
+    }
+
+    #[test]
+    fn check_digit_mismatch_is_rejected() {
+        assert_eq!(
+            Cb32u128::from_str_with_check("Z00"),
+            Err(Cb32u128ParseError::CheckDigitMismatch {
+                expected: 30,
+                found: 0
+            })
+        );
+    }
+
+    #[test]
+    fn binary_round_trips() {
+        for value in [0u128, 1, 127, 128, u128::MAX] {
+            let id = Cb32u128::from(value);
+            let bytes = id.encode_to();
+            assert_eq!(Cb32u128::decode_from(&bytes), Ok((id, &[][..])));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bincode_round_trip_uses_the_binary_encoding() {
+        // a small value should cost a byte or two, not the 8-byte length prefix bincode would add
+        // for a `Vec<u8>`/`serialize_bytes` on top of the LEB128 payload
+        let small = Cb32u128::from(5);
+        let small_bytes = bincode::serialize(&small).unwrap();
+        assert_eq!(small_bytes.len(), 1);
+        assert_eq!(bincode::deserialize::<Cb32u128>(&small_bytes).unwrap(), small);
+
+        let id = Cb32u128::from(u128::MAX);
+        let bytes = bincode::serialize(&id).unwrap();
+        // a textual serializer would need 26 bytes just for the digits
+        assert!(bytes.len() < 26);
+
+        let decoded: Cb32u128 = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, id);
+    }
+
+    #[test]
+    fn debug_keeps_the_concrete_type_name() {
+        assert_eq!(format!("{:?}", Cb32u128::from(0)), "Cb32u128(0)");
+    }
 }
@@ -1,93 +1,296 @@
 use std::{
-    fmt::{Debug, Display, Write}, hash::{DefaultHasher, Hash, Hasher}, ops::Deref, str::FromStr
+    fmt::{Debug, Display},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use num::Num;
 use radix_fmt::radix_36;
 use rand::{random, seq::SliceRandom, thread_rng, Rng};
+use sha3::{Digest, Sha3_256};
 use thiserror::Error;
 
+pub const MIN_LENGTH: u8 = 2;
+/// The highest length a `u128` can hold regardless of which first letter gets picked: a
+/// `length`-digit base36 number maxes out at `36^length - 1`, and `36^24` is the last power that
+/// always stays under `u128::MAX` no matter the leading digit (`36^25` already overflows for
+/// about three quarters of the possible first letters).
+///
+/// The real CUID2 spec allows lengths up to 32; this crate can only go up to 24 because the
+/// payload is a single `u128`. Supporting 25..=32 would mean widening the payload's
+/// representation (e.g. to a byte buffer) rather than an arithmetic limit that can be raised here.
+pub const MAX_LENGTH: u8 = 24;
+pub const DEFAULT_LENGTH: u8 = 24;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
-pub struct Cuid2(u128);
+pub struct Cuid2 {
+    value: u128,
+    length: u8,
+}
 
 impl Debug for Cuid2 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Cuid2(\"{}\")", radix_36(self.0))
+        write!(f, "Cuid2(\"{self}\")")
     }
 }
 
 impl Display for Cuid2 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", radix_36(self.0))
+        write!(
+            f,
+            "{:0>width$}",
+            radix_36(self.value),
+            width = self.length as usize
+        )
     }
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum Cuid2ParseError {
-    #[error("wrong length (required 24)")]
+    #[error("wrong length (must be between {MIN_LENGTH} and {MAX_LENGTH} characters)")]
     WrongLength,
     #[error("illegal character")]
     IllegalCharacter,
 }
 
+/// Error returned by [Cuid2Builder::length] when asked for a length outside
+/// [MIN_LENGTH]..=[MAX_LENGTH].
+///
+/// Note that the real CUID2 spec allows lengths up to 32, but this crate represents the id's
+/// payload as a single `u128`, which can only ever hold up to [MAX_LENGTH] base-36 digits (see
+/// the doc comment on [MAX_LENGTH]) — so 25..=32 are rejected here rather than silently
+/// truncated or allowed to panic later.
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Cuid2 length must be between {MIN_LENGTH} and {MAX_LENGTH}, got {0}")]
+pub struct Cuid2LengthError(u8);
+
 impl FromStr for Cuid2 {
     type Err = Cuid2ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 24 {
+        let length = s.len();
+        if !(MIN_LENGTH as usize..=MAX_LENGTH as usize).contains(&length) {
             return Err(Cuid2ParseError::WrongLength);
         }
-        let encoded = u128::from_str_radix(s, 36).map_err(|_| Cuid2ParseError::IllegalCharacter)?;
-        Ok(Cuid2(encoded))
+        let value = u128::from_str_radix(s, 36).map_err(|_| Cuid2ParseError::IllegalCharacter)?;
+        Ok(Cuid2 {
+            value,
+            length: length as u8,
+        })
     }
 }
 
-pub fn pseudo_cuid2() -> Cuid2 {
-    pseudo_cuid2_from_rng(&mut thread_rng())
+/// Generates a [Cuid2] with the default length, using `thread_rng()` and this process's
+/// fingerprint. See [Cuid2Builder] to customize the length, RNG or fingerprint.
+pub fn cuid2() -> Cuid2 {
+    Cuid2Builder::default().build()
 }
 
-pub fn pseudo_cuid2_from_rng(rng: &mut (impl Rng + ?Sized)) -> Cuid2 {
-    let process_id = std::process::id();
-    let thread_id = std::thread::current().id();
-    let time = std::time::SystemTime::now();
-    let entropy: u64 = rng.gen();
+/// Like [cuid2], but draws entropy from the given RNG instead of `thread_rng()`.
+pub fn cuid2_from_rng(rng: &mut (impl Rng + ?Sized)) -> Cuid2 {
+    Cuid2Builder::default().build_from_rng(rng)
+}
 
-    let mut hasher = DefaultHasher::new();
-    process_id.hash(&mut hasher);
-    thread_id.hash(&mut hasher);
-    time.hash(&mut hasher);
-    entropy.hash(&mut hasher);
+/// Builds [Cuid2]s with a configurable length, RNG and host fingerprint.
+///
+/// ```rust
+/// use humanoid::cuid2::Cuid2Builder;
+///
+/// let id = Cuid2Builder::new().length(10).unwrap().build();
+/// assert_eq!(id.to_string().len(), 10);
+/// ```
+#[derive(Debug)]
+pub struct Cuid2Builder {
+    length: u8,
+    fingerprint: Option<[u8; 32]>,
+}
 
-    let hi = hasher.finish();
-    let lo = random();
-    let bytes = [hi, lo];
+impl Cuid2Builder {
+    pub fn new() -> Self {
+        Cuid2Builder {
+            length: DEFAULT_LENGTH,
+            fingerprint: None,
+        }
+    }
 
-    let x = u128::from_ne_bytes(unsafe { std::mem::transmute(bytes) });
+    /// Sets the length of generated ids.
+    ///
+    /// # Errors
+    /// Returns [Cuid2LengthError] if `length` is outside [MIN_LENGTH]..=[MAX_LENGTH].
+    pub fn length(mut self, length: u8) -> Result<Self, Cuid2LengthError> {
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) {
+            return Err(Cuid2LengthError(length));
+        }
+        self.length = length;
+        Ok(self)
+    }
+
+    /// Overrides the host fingerprint that would otherwise be computed once per process.
+    pub fn fingerprint(mut self, fingerprint: [u8; 32]) -> Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    pub fn build(&self) -> Cuid2 {
+        self.build_from_rng(&mut thread_rng())
+    }
+
+    pub fn build_from_rng(&self, rng: &mut (impl Rng + ?Sized)) -> Cuid2 {
+        generate(
+            self.length,
+            rng,
+            self.fingerprint.unwrap_or_else(host_fingerprint),
+        )
+    }
+}
+
+impl Default for Cuid2Builder {
+    fn default() -> Self {
+        Cuid2Builder::new()
+    }
+}
+
+/// Per-process counter mixed into every generated id, so ids produced in quick succession from
+/// the same process still differ even if the clock and entropy happened to collide.
+static SESSION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A per-process fingerprint (hostname + pid + a random salt), computed once and reused by every
+/// [Cuid2Builder] that doesn't supply its own via [Cuid2Builder::fingerprint].
+fn host_fingerprint() -> [u8; 32] {
+    static FINGERPRINT: OnceLock<[u8; 32]> = OnceLock::new();
+    *FINGERPRINT.get_or_init(|| {
+        let pid = std::process::id();
+        let hostname = std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .unwrap_or_default();
+        let salt: u64 = random();
+
+        let mut hasher = Sha3_256::new();
+        hasher.update(pid.to_be_bytes());
+        hasher.update(hostname.as_bytes());
+        hasher.update(salt.to_be_bytes());
+
+        hasher.finalize().into()
+    })
+}
+
+fn generate(length: u8, rng: &mut (impl Rng + ?Sized), fingerprint: [u8; 32]) -> Cuid2 {
+    assert!(
+        (MIN_LENGTH..=MAX_LENGTH).contains(&length),
+        "Cuid2 length must be between {MIN_LENGTH} and {MAX_LENGTH}, got {length}"
+    );
+
+    let counter = SESSION_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let timestamp_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time is before the Unix epoch")
+        .as_millis();
+    let entropy: u128 = rng.gen();
+
+    let mut hasher = Sha3_256::new();
+    hasher.update(timestamp_ms.to_be_bytes());
+    hasher.update(counter.to_be_bytes());
+    hasher.update(fingerprint);
+    hasher.update(entropy.to_be_bytes());
+    let digest = hasher.finalize();
+    let digest_value = u128::from_be_bytes(digest[..16].try_into().unwrap());
 
     let random_first_letter: char = (*"abcdefghijklmnopqrstuvwxyz"
         .as_bytes()
-        .choose(&mut thread_rng())
+        .choose(rng)
         .unwrap())
     .into();
+    let first_letter_digit = random_first_letter
+        .to_digit(36)
+        .expect("ascii lowercase letter is a valid base36 digit") as u128;
+
+    // `length`-1 base36 digits of the digest, left-padded with zeros, sit after the leading
+    // letter. Building the value arithmetically (rather than formatting the full `length`-digit
+    // string and re-parsing it with `u128::from_str_radix`) avoids ever needing to represent a
+    // base36 number wider than `MAX_LENGTH` digits, which is the one guaranteed to fit `u128`.
+    let digits_width = (length - 1) as u32;
+    let modulus = 36u128.pow(digits_width);
+    let value = first_letter_digit * modulus + (digest_value % modulus);
 
-    let mut buffer = String::with_capacity(24);
-    write!(buffer, "{}{:0>23}", random_first_letter, radix_36(x)).unwrap();
-    buffer.truncate(24);
+    Cuid2 { value, length }
+}
 
-    let x: u128 = u128::from_str_radix(&buffer, 36).unwrap();
+impl Cuid2 {
+    /// Encodes the inner `u128` as an unsigned LEB128 varint, for compact storage in binary
+    /// formats where the textual form (see [Display]) would be wasteful.
+    pub fn encode_to(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        crate::leb128::encode(self.value, &mut out);
+        out
+    }
 
-    Cuid2(x)
+    /// Decodes a value previously produced by [Cuid2::encode_to], returning it along with any
+    /// unconsumed trailing bytes. The decoded id gets [DEFAULT_LENGTH] as its display length,
+    /// since that information isn't carried by the binary encoding.
+    pub fn decode_from(bytes: &[u8]) -> Result<(Self, &[u8]), crate::leb128::DecodeError> {
+        let (value, rest) = crate::leb128::decode(bytes)?;
+        Ok((
+            Cuid2 {
+                value,
+                length: DEFAULT_LENGTH,
+            },
+            rest,
+        ))
+    }
 }
 
 #[cfg(feature = "rand")]
 mod rand_impls {
     use rand::distributions::{Distribution, Standard};
 
-    use super::{pseudo_cuid2_from_rng, Cuid2};
+    use super::{cuid2_from_rng, Cuid2};
 
     impl Distribution<Cuid2> for Standard {
         fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Cuid2 {
-            pseudo_cuid2_from_rng(rng)
+            cuid2_from_rng(rng)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use std::str::FromStr;
+
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Cuid2, DEFAULT_LENGTH};
+
+    impl Serialize for Cuid2 {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            if serializer.is_human_readable() {
+                serializer.collect_str(self)
+            } else {
+                crate::leb128::serde_support::serialize(self.value, serializer)
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Cuid2 {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                let s = String::deserialize(deserializer)?;
+                Cuid2::from_str(&s).map_err(|e| D::Error::custom(e.to_string()))
+            } else {
+                let value = crate::leb128::serde_support::deserialize(deserializer)?;
+                Ok(Cuid2 {
+                    value,
+                    length: DEFAULT_LENGTH,
+                })
+            }
         }
     }
 }
@@ -98,10 +301,80 @@ mod test {
 
     #[test]
     fn stuff() {
-        println!("{}", pseudo_cuid2());
-        println!("{:?}", pseudo_cuid2());
-        println!("{}", pseudo_cuid2());
-        println!("{:?}", pseudo_cuid2());
-        println!("{}", pseudo_cuid2());
+        println!("{}", cuid2());
+        println!("{:?}", cuid2());
+        println!("{}", cuid2());
+        println!("{:?}", cuid2());
+        println!("{}", cuid2());
+    }
+
+    #[test]
+    fn configurable_length_round_trips() {
+        let id = Cuid2Builder::new().length(10).unwrap().build();
+        assert_eq!(id.to_string().len(), 10);
+        assert_eq!(id.to_string().parse(), Ok(id));
+    }
+
+    #[test]
+    fn max_length_never_panics_regardless_of_first_letter() {
+        // Regression test: MAX_LENGTH used to be 32, and re-parsing the padded
+        // letter+digest buffer through `u128::from_str_radix` panicked for any length >= 26,
+        // and for most first letters at length 25. Run enough iterations that, with
+        // overwhelming probability, every one of the 26 possible first letters gets exercised.
+        for _ in 0..2000 {
+            let id = Cuid2Builder::new().length(MAX_LENGTH).unwrap().build();
+            assert_eq!(id.to_string().len(), MAX_LENGTH as usize);
+            assert_eq!(id.to_string().parse(), Ok(id));
+        }
+    }
+
+    #[test]
+    fn length_out_of_range_is_an_error_not_a_panic() {
+        assert_eq!(
+            Cuid2Builder::new().length(MIN_LENGTH - 1).unwrap_err(),
+            Cuid2LengthError(MIN_LENGTH - 1)
+        );
+        assert_eq!(
+            Cuid2Builder::new().length(MAX_LENGTH + 1).unwrap_err(),
+            Cuid2LengthError(MAX_LENGTH + 1)
+        );
+    }
+
+    #[test]
+    fn binary_round_trips() {
+        for value in [0u128, 1, 127, 128, u128::MAX] {
+            let id = Cuid2 { value, length: DEFAULT_LENGTH };
+            let bytes = id.encode_to();
+            assert_eq!(Cuid2::decode_from(&bytes), Ok((id, &[][..])));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bincode_round_trip_uses_the_binary_encoding() {
+        // a small value should cost a byte or two, not the 8-byte length prefix bincode would add
+        // for a `Vec<u8>`/`serialize_bytes` on top of the LEB128 payload
+        let small = Cuid2 {
+            value: 5,
+            length: DEFAULT_LENGTH,
+        };
+        let small_bytes = bincode::serialize(&small).unwrap();
+        assert_eq!(small_bytes.len(), 1);
+
+        let id = cuid2();
+
+        let bytes = bincode::serialize(&id).unwrap();
+        // a textual serializer would need `length` bytes just for the digits
+        assert!(bytes.len() < id.length as usize);
+
+        let decoded: Cuid2 = bincode::deserialize(&bytes).unwrap();
+        // the binary deserializer can't know the original display length, so it falls back to
+        // the default
+        assert_eq!(decoded.to_string(), id.to_string());
+    }
+
+    #[test]
+    fn default_length_is_24() {
+        assert_eq!(cuid2().to_string().len(), 24);
     }
 }
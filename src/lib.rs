@@ -10,9 +10,182 @@ use thiserror::Error;
 #[cfg(feature = "cuid2")]
 pub mod cuid2;
 
+#[cfg(feature = "cb32u128")]
+pub mod alphabet;
+
 #[cfg(feature = "cb32u128")]
 pub mod cb32u128;
 
+#[cfg(feature = "ulid")]
+pub mod ulid;
+
+/// Unsigned LEB128 varint encoding, used by [cb32u128::Cb32u128]/[cuid2::Cuid2] to serialize
+/// their inner `u128` compactly in non-human-readable formats (see their `encode_to`/
+/// `decode_from`).
+pub mod leb128 {
+    /// Appends `value`'s LEB128 encoding to `out`: 7-bit little-endian groups, with the high bit
+    /// of every byte but the last set as a continuation flag.
+    pub(crate) fn encode(mut value: u128, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value != 0 {
+                out.push(byte | 0x80);
+            } else {
+                out.push(byte);
+                break;
+            }
+        }
+    }
+
+    /// Why a [decode] call failed. `pub` (rather than `pub(crate)`) because it's reachable from
+    /// the public `decode_from` methods on [crate::cb32u128::Cb32u128]/[crate::cuid2::Cuid2].
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum DecodeError {
+        UnexpectedEof,
+        Overflow,
+    }
+
+    /// Decodes a single LEB128 varint from the front of `bytes`, returning the value and the
+    /// remaining, unconsumed bytes.
+    pub(crate) fn decode(bytes: &[u8]) -> Result<(u128, &[u8]), DecodeError> {
+        let mut result: u128 = 0;
+        let mut shift: u32 = 0;
+
+        for (i, &byte) in bytes.iter().enumerate() {
+            if shift >= 128 {
+                return Err(DecodeError::Overflow);
+            }
+
+            let low7 = (byte & 0x7f) as u128;
+            // If fewer than 7 bits of budget remain, this byte must not carry any bits past
+            // that budget - otherwise `<< shift` would silently drop them instead of erroring.
+            let remaining = 128 - shift;
+            if remaining < 7 && (low7 >> remaining) != 0 {
+                return Err(DecodeError::Overflow);
+            }
+
+            result |= low7 << shift;
+            if byte & 0x80 == 0 {
+                return Ok((result, &bytes[i + 1..]));
+            }
+            shift += 7;
+        }
+
+        Err(DecodeError::UnexpectedEof)
+    }
+
+    /// Serializes/deserializes LEB128 bytes as a fixed-arity tuple rather than a length-prefixed
+    /// byte sequence, so binary formats that aren't self-describing (e.g. bincode, which writes
+    /// an 8-byte length prefix for `serialize_bytes`/`Vec<u8>`) don't tack framing on top of
+    /// LEB128's own continuation bit - which already makes the encoding self-delimiting. Used by
+    /// [crate::cb32u128::Cb32u128]/[crate::cuid2::Cuid2]'s non-human-readable serde impls.
+    #[cfg(feature = "serde")]
+    pub(crate) mod serde_support {
+        use std::fmt;
+
+        use serde::{de::Error as _, ser::SerializeTuple, Deserializer, Serializer};
+
+        use super::{decode, encode, DecodeError};
+
+        /// `ceil(128 / 7)`: the most bytes a `u128`'s LEB128 encoding can ever need, used as the
+        /// tuple's arity so it's large enough for any value without ever writing an actual length.
+        const MAX_ENCODED_LEN: usize = 19;
+
+        pub(crate) fn serialize<S: Serializer>(value: u128, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut bytes = Vec::new();
+            encode(value, &mut bytes);
+
+            let mut tup = serializer.serialize_tuple(bytes.len())?;
+            for byte in &bytes {
+                tup.serialize_element(byte)?;
+            }
+            tup.end()
+        }
+
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+            deserializer.deserialize_tuple(MAX_ENCODED_LEN, LebVisitor)
+        }
+
+        struct LebVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for LebVisitor {
+            type Value = u128;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "an unsigned LEB128 varint")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut bytes = Vec::with_capacity(MAX_ENCODED_LEN);
+                loop {
+                    let byte: u8 = seq
+                        .next_element()?
+                        .ok_or_else(|| A::Error::custom("truncated LEB128 varint"))?;
+                    let is_last = byte & 0x80 == 0;
+                    bytes.push(byte);
+                    if is_last {
+                        break;
+                    }
+                    if bytes.len() == MAX_ENCODED_LEN {
+                        return Err(A::Error::custom(format!("{:?}", DecodeError::Overflow)));
+                    }
+                }
+
+                let (value, rest) = decode(&bytes).map_err(|e| A::Error::custom(format!("{e:?}")))?;
+                debug_assert!(rest.is_empty());
+                Ok(value)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn round_trip(value: u128) {
+            let mut buf = Vec::new();
+            encode(value, &mut buf);
+            assert_eq!(decode(&buf), Ok((value, &[][..])));
+        }
+
+        #[test]
+        fn round_trips_boundary_values() {
+            round_trip(0);
+            round_trip(1);
+            round_trip(127);
+            round_trip(128);
+            round_trip(u128::MAX);
+        }
+
+        #[test]
+        fn decode_leaves_trailing_bytes_unconsumed() {
+            let mut buf = Vec::new();
+            encode(42, &mut buf);
+            buf.extend_from_slice(&[1, 2, 3]);
+            assert_eq!(decode(&buf), Ok((42, &[1, 2, 3][..])));
+        }
+
+        #[test]
+        fn decode_rejects_unexpected_eof() {
+            assert_eq!(decode(&[0x80, 0x80]), Err(DecodeError::UnexpectedEof));
+        }
+
+        #[test]
+        fn decode_rejects_a_final_byte_that_would_silently_truncate() {
+            // 18 continuation bytes followed by a final byte whose high bits don't fit the
+            // remaining 2-bit budget - regression test for a gap where only bytes *past* the
+            // 19th ever tripped the overflow check, silently truncating this one instead.
+            let mut bytes = vec![0x80; 18];
+            bytes.push(0x7f);
+            assert_eq!(decode(&bytes), Err(DecodeError::Overflow));
+        }
+    }
+}
+
 pub trait Prefix {
     const VALUE: &str;
 }
@@ -201,20 +374,31 @@ mod serde_impls {
     use super::*;
     use serde::{de::Visitor, Deserialize, Serialize, Serializer};
 
-    impl<P: Prefix, T: Display> Serialize for PrefixedId<P, T> {
+    // Human-readable formats (e.g. JSON) always get the full textual `prefix_id` form. Binary
+    // formats (e.g. bincode/postcard) skip it entirely and just serialize the payload `T` -
+    // the prefix is a compile-time constant, so there's nothing to gain from writing it out.
+    impl<P: Prefix, T: Display + Serialize> Serialize for PrefixedId<P, T> {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
         {
-            serializer.collect_str(self)
+            if serializer.is_human_readable() {
+                serializer.collect_str(self)
+            } else {
+                self.0.serialize(serializer)
+            }
         }
     }
 
-    impl<'de, P: Prefix, T: FromStr> Deserialize<'de> for PrefixedId<P, T> {
+    impl<'de, P: Prefix, T: FromStr + Deserialize<'de>> Deserialize<'de> for PrefixedId<P, T> {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
             D: serde::Deserializer<'de>,
         {
+            if !deserializer.is_human_readable() {
+                return T::deserialize(deserializer).map(PrefixedId::from_id);
+            }
+
             struct V<P, T>(PhantomData<(P, T)>);
             impl<'de, P: Prefix, T: FromStr> Visitor<'de> for V<P, T> {
                 type Value = PrefixedId<P, T>;
@@ -0,0 +1,218 @@
+use std::{
+    fmt::{self, Debug, Display},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rand::Rng;
+
+use crate::cb32u128::{Cb32u128, Cb32u128ParseError};
+
+const TIMESTAMP_BITS: u32 = 48;
+const RANDOM_BITS: u32 = 80;
+const RANDOM_MASK: u128 = (1 << RANDOM_BITS) - 1;
+
+/// A [ULID](https://github.com/ulid/spec): a 48-bit Unix-millisecond timestamp in the high bits
+/// and 80 bits of randomness in the low bits of a `u128`, printed via [Cb32u128]'s Crockford
+/// Base32 [Display]/[FromStr] as a left-padded 26-char string. Lexical ordering of the string
+/// form matches creation order, which makes it a database-index-friendly alternative to
+/// [crate::cuid2::Cuid2] when you need sortable ids.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ulid(u128);
+
+impl Ulid {
+    /// Builds a [Ulid] from its parts directly; `random` is masked down to 80 bits.
+    ///
+    /// # Panics
+    /// Panics if `ts_ms` doesn't fit in 48 bits.
+    pub fn from_parts(ts_ms: u64, random: u128) -> Self {
+        assert!(
+            ts_ms < (1u64 << TIMESTAMP_BITS),
+            "Ulid timestamp does not fit in 48 bits"
+        );
+        Ulid(((ts_ms as u128) << RANDOM_BITS) | (random & RANDOM_MASK))
+    }
+
+    /// Generates a new [Ulid] from the current time and fresh randomness.
+    pub fn now() -> Self {
+        Ulid::from_parts(current_timestamp_ms(), rand::random::<u128>())
+    }
+
+    pub fn timestamp_ms(&self) -> u64 {
+        (self.0 >> RANDOM_BITS) as u64
+    }
+
+    pub fn random(&self) -> u128 {
+        self.0 & RANDOM_MASK
+    }
+}
+
+fn current_timestamp_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time is before the Unix epoch")
+        .as_millis() as u64
+}
+
+impl Display for Ulid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = Cb32u128::from(self.0).to_string();
+        for _ in digits.len()..26 {
+            write!(f, "0")?;
+        }
+        write!(f, "{digits}")
+    }
+}
+
+impl Debug for Ulid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ulid({self})")
+    }
+}
+
+impl FromStr for Ulid {
+    type Err = Cb32u128ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cb32: Cb32u128 = s.parse()?;
+        Ok(Ulid(cb32.into()))
+    }
+}
+
+/// Generates [Ulid]s that are guaranteed to be monotonically increasing (as strings, and as the
+/// underlying `u128`) even when several are requested within the same millisecond: instead of
+/// re-randomizing, the random component is incremented, carrying into the timestamp on the rare
+/// occasion the 80-bit field overflows.
+pub struct MonotonicUlidGenerator {
+    last_ts_ms: u64,
+    last_random: u128,
+}
+
+impl MonotonicUlidGenerator {
+    pub fn new() -> Self {
+        MonotonicUlidGenerator {
+            last_ts_ms: 0,
+            last_random: 0,
+        }
+    }
+
+    pub fn generate(&mut self) -> Ulid {
+        self.generate_from_rng(&mut rand::thread_rng())
+    }
+
+    pub fn generate_from_rng(&mut self, rng: &mut (impl Rng + ?Sized)) -> Ulid {
+        // Clamp to `last_ts_ms`: if the system clock ever steps backward (NTP correction, VM
+        // migration, leap-second smear), treating that as "same millisecond" and incrementing the
+        // random component keeps ids increasing instead of silently resetting to a smaller one.
+        let ts_ms = current_timestamp_ms().max(self.last_ts_ms);
+
+        if ts_ms == self.last_ts_ms {
+            self.last_random = (self.last_random + 1) & RANDOM_MASK;
+            if self.last_random == 0 {
+                // the 80-bit random field overflowed; carry into the timestamp so the id is
+                // still greater than the previous one
+                self.last_ts_ms += 1;
+            }
+        } else {
+            self.last_ts_ms = ts_ms;
+            self.last_random = rng.gen::<u128>() & RANDOM_MASK;
+        }
+
+        Ulid::from_parts(self.last_ts_ms, self.last_random)
+    }
+}
+
+impl Default for MonotonicUlidGenerator {
+    fn default() -> Self {
+        MonotonicUlidGenerator::new()
+    }
+}
+
+#[cfg(feature = "rand")]
+mod rand_impls {
+    use rand::distributions::{Distribution, Standard};
+
+    use super::*;
+
+    impl Distribution<Ulid> for Standard {
+        fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Ulid {
+            Ulid::from_parts(current_timestamp_ms(), rng.gen())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    impl Serialize for Ulid {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            if serializer.is_human_readable() {
+                serializer.collect_str(self)
+            } else {
+                Cb32u128::from(self.0).serialize(serializer)
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Ulid {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let cb32 = Cb32u128::deserialize(deserializer)?;
+            Ok(Ulid(cb32.into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_display_from_str() {
+        let ulid = Ulid::from_parts(1_700_000_000_000, 0x1234);
+        assert_eq!(ulid.to_string().len(), 26);
+        assert_eq!(ulid.to_string().parse(), Ok(ulid));
+    }
+
+    #[test]
+    fn monotonic_generator_is_increasing_even_if_the_clock_steps_backward() {
+        // Simulate a backward clock step by seeding `last_ts_ms` ahead of the real current time
+        // (e.g. an NTP correction or VM migration moving the system clock back).
+        let mut gen = MonotonicUlidGenerator {
+            last_ts_ms: current_timestamp_ms() + 10_000,
+            last_random: 0,
+        };
+
+        let a = gen.generate_from_rng(&mut rand::thread_rng());
+        let b = gen.generate_from_rng(&mut rand::thread_rng());
+
+        assert!(b > a);
+        assert_eq!(a.timestamp_ms(), b.timestamp_ms());
+    }
+
+    #[test]
+    fn monotonic_generator_is_increasing_within_the_same_millisecond() {
+        // Seed `last_ts_ms` ahead of the real current time, same as the backward-clock test
+        // above, so `generate_from_rng` deterministically takes the same-millisecond increment
+        // path instead of the fresh-random one (which it would if seeded in the past and the
+        // two wall-clock reads happened to land in different milliseconds).
+        let mut gen = MonotonicUlidGenerator {
+            last_ts_ms: current_timestamp_ms() + 10_000,
+            last_random: 0,
+        };
+
+        let a = gen.generate_from_rng(&mut rand::thread_rng());
+        let b = gen.generate_from_rng(&mut rand::thread_rng());
+
+        assert!(b > a);
+        assert_eq!(a.timestamp_ms(), b.timestamp_ms());
+    }
+}